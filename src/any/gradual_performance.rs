@@ -0,0 +1,102 @@
+use crate::{
+    catch::CatchGradualPerformanceAttributes, mania::ManiaGradualPerformanceAttributes,
+    osu::OsuGradualPerformanceAttributes, taiko::TaikoGradualPerformanceAttributes, Beatmap,
+    GameMode,
+};
+
+use super::{PerformanceAttributes, ScoreState};
+
+/// Gradually calculate the performance attributes of a map, regardless of its mode.
+///
+/// This is the mode-agnostic counterpart to e.g.
+/// [`OsuGradualPerformanceAttributes`](crate::osu::OsuGradualPerformanceAttributes),
+/// letting callers process a map one hit object at a time without knowing its
+/// mode at compile time.
+///
+/// After each hit object you can call
+/// [`process_next_object`](GradualPerformanceAttributes::process_next_object)
+/// and it will return the resulting current [`PerformanceAttributes`]. To
+/// process multiple objects at once, use
+/// [`process_next_n_objects`](GradualPerformanceAttributes::process_next_n_objects) instead.
+#[derive(Debug)]
+pub enum GradualPerformanceAttributes<'map> {
+    Osu(OsuGradualPerformanceAttributes<'map>),
+    Taiko(TaikoGradualPerformanceAttributes<'map>),
+    Catch(CatchGradualPerformanceAttributes<'map>),
+    Mania(ManiaGradualPerformanceAttributes<'map>),
+}
+
+impl<'map> GradualPerformanceAttributes<'map> {
+    /// Create a new mode-agnostic gradual performance calculator for the given map.
+    ///
+    /// The map's own [`GameMode`] decides which per-mode calculator is used internally.
+    pub fn new(map: &'map Beatmap, mods: u32) -> Self {
+        match map.mode {
+            GameMode::Osu => Self::Osu(OsuGradualPerformanceAttributes::new(map, mods)),
+            GameMode::Taiko => Self::Taiko(TaikoGradualPerformanceAttributes::new(map, mods)),
+            GameMode::Catch => Self::Catch(CatchGradualPerformanceAttributes::new(map, mods)),
+            GameMode::Mania => Self::Mania(ManiaGradualPerformanceAttributes::new(map, mods)),
+        }
+    }
+
+    /// Process the next hit object and calculate the
+    /// performance attributes for the resulting score state.
+    pub fn process_next_object(&mut self, state: ScoreState) -> Option<PerformanceAttributes> {
+        self.process_next_n_objects(state, 1)
+    }
+
+    /// Same as [`process_next_object`](GradualPerformanceAttributes::process_next_object)
+    /// but instead of processing only one object it processes `n` many.
+    ///
+    /// If `n` is 0 it will be considered as 1.
+    /// If there are still objects to be processed but `n` is larger than the amount
+    /// of remaining objects, `n` will be considered as the amount of remaining objects.
+    ///
+    /// Panics if `state`'s variant does not match the map's mode.
+    pub fn process_next_n_objects(
+        &mut self,
+        state: ScoreState,
+        n: usize,
+    ) -> Option<PerformanceAttributes> {
+        match (self, state) {
+            (Self::Osu(gradual), ScoreState::Osu(state)) => gradual
+                .process_next_n_objects(state, n)
+                .map(PerformanceAttributes::Osu),
+            (Self::Taiko(gradual), ScoreState::Taiko(state)) => gradual
+                .process_next_n_objects(state, n)
+                .map(PerformanceAttributes::Taiko),
+            (Self::Catch(gradual), ScoreState::Catch(state)) => gradual
+                .process_next_n_objects(state, n)
+                .map(PerformanceAttributes::Catch),
+            (Self::Mania(gradual), ScoreState::Mania(state)) => gradual
+                .process_next_n_objects(state, n)
+                .map(PerformanceAttributes::Mania),
+            _ => panic!("given score state does not match the map's mode"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn new_picks_the_calculator_matching_the_map_mode() {
+        let mut map = Beatmap::default();
+        map.mode = GameMode::Osu;
+
+        let gradual = GradualPerformanceAttributes::new(&map, 0);
+
+        assert!(matches!(gradual, GradualPerformanceAttributes::Osu(_)));
+    }
+
+    #[test]
+    #[should_panic(expected = "does not match the map's mode")]
+    fn process_next_object_panics_on_mode_mismatch() {
+        let mut map = Beatmap::default();
+        map.mode = GameMode::Osu;
+
+        let mut gradual = GradualPerformanceAttributes::new(&map, 0);
+        gradual.process_next_object(ScoreState::Taiko(Default::default()));
+    }
+}
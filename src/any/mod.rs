@@ -3,11 +3,13 @@ pub use self::{
         AttributeProvider, DifficultyAttributes, ModeAttributeProvider, PerformanceAttributes,
     },
     difficulty::{Difficulty, ModeDifficulty},
+    gradual_performance::GradualPerformanceAttributes,
     performance::{HitResultPriority, Performance},
     score_state::ScoreState,
 };
 
 mod attributes;
 pub(crate) mod difficulty;
+mod gradual_performance;
 mod performance;
 mod score_state;
\ No newline at end of file
@@ -1,61 +1,23 @@
-use crate::{Beatmap, OsuPP};
-use super::{OsuGradualDifficultyAttributes, OsuPerformanceAttributes};
-
-/// Aggregation for a score's current state i.e. what was the
-/// maximum combo so far and what are the current hitresults.
+use crate::Beatmap;
+use super::{
+    pp::{OsuPP, OsuScoreState},
+    stars::OsuDifficultyAttributes,
+    OsuGradualDifficultyAttributes, OsuPerformanceAttributes,
+};
+
+/// Counts how many strains of a skill are close to its hardest one.
 ///
-/// This struct is used for [`OsuGradualPerformanceAttributes`].
-#[derive(Clone, Debug, Default, Eq, PartialEq)]
-pub struct OsuScoreState {
-    /// Maximum combo that the score has had so far.
-    /// **Not** the maximum possible combo of the map so far.
-    pub max_combo: usize,
-    /// Amount of current 300s.
-    pub n300: usize,
-    /// Amount of current 100s.
-    pub n100: usize,
-    /// Amount of current 50s.
-    pub n50: usize,
-    /// Amount of current misses.
-    pub n_misses: usize,
-}
-
-impl OsuScoreState {
-    /// Create a new empty score state.
-    #[inline]
-    pub fn new() -> Self {
-        Self::default()
-    }
-
-    /// Return the total amount of hits by adding everything up.
-    #[inline]
-    pub fn total_hits(&self) -> usize {
-        self.n300 + self.n100 + self.n50 + self.n_misses
-    }
-
-    /// Calculate the accuracy between `0.0` and `1.0` for this state.
-    #[inline]
-    pub fn accuracy(&self) -> f64 {
-        let total_hits = self.total_hits();
-
-        if total_hits == 0 {
-            return 0.0;
-        }
-
-        let numerator = 6 * self.n300 + 2 * self.n100 + self.n50;
-        let denominator = 6 * total_hits;
-
-        numerator as f64 / denominator as f64
+/// Returns `0.0` if the skill has no strain at all. Otherwise every strain
+/// contributes `(strain / max_strain)^4`, so a map whose difficulty sits in a
+/// single huge spike scores much lower here than one with uniformly high strain.
+pub fn difficult_strain_count(strains: &[f64]) -> f64 {
+    let max_strain = strains.iter().cloned().fold(0.0_f64, f64::max);
+
+    if max_strain == 0.0 {
+        return 0.0;
     }
-}
 
-/// Trait for providing osu!standard difficulty attributes.
-pub trait OsuAttributeProvider {
-    fn get_aim(&self) -> f64;
-    fn get_speed(&self) -> f64;
-    fn get_overall_difficulty(&self) -> f64;
-    fn get_approach_rate(&self) -> f64;
-    fn get_max_combo(&self) -> usize;
+    strains.iter().map(|strain| (strain / max_strain).powi(4)).sum()
 }
 
 /// Gradually calculate the performance attributes of an osu!standard map.
@@ -71,6 +33,11 @@ pub trait OsuAttributeProvider {
 ///
 /// If you only want to calculate difficulty attributes use
 /// [`OsuGradualDifficultyAttributes`](crate::osu::OsuGradualDifficultyAttributes) instead.
+///
+/// This already is the per-object gradual performance subsystem for
+/// osu!standard: each call reuses `OsuGradualDifficultyAttributes`'s
+/// incrementally-updated strains rather than recomputing the whole map's
+/// difficulty from scratch. Don't add another one of these.
 #[derive(Debug)]
 pub struct OsuGradualPerformanceAttributes<'map> {
     difficulty: OsuGradualDifficultyAttributes,
@@ -116,54 +83,94 @@ impl<'map> OsuGradualPerformanceAttributes<'map> {
             .performance
             .clone()
             .attributes(difficulty)
-            .state(state)
+            .n300(state.n300)
+            .n100(state.n100)
+            .n50(state.n50)
+            .misses(state.n_misses)
+            .combo(state.max_combo)
             .passed_objects(self.difficulty.idx + 1)
             .calculate();
 
         Some(performance)
     }
-}
 
-/// Struct representing osu!standard difficulty attributes.
-#[derive(Debug, Clone)]
-pub struct OsuDifficultyAttributes {
-    pub aim: f64,
-    pub speed: f64,
-    pub overall_difficulty: f64,
-    pub approach_rate: f64,
-    pub max_combo: usize,
+    /// Process a stream of per-object score states and collect the
+    /// resulting performance attributes after each one.
+    ///
+    /// Equivalent to calling
+    /// [`process_next_object`](OsuGradualPerformanceAttributes::process_next_object)
+    /// for each state in order.
+    pub fn process<I>(&mut self, states: I) -> Vec<OsuPerformanceAttributes>
+    where
+        I: IntoIterator<Item = OsuScoreState>,
+    {
+        states
+            .into_iter()
+            .filter_map(|state| self.process_next_object(state))
+            .collect()
+    }
 }
 
-/// Implement the OsuAttributeProvider trait for OsuDifficultyAttributes.
-impl OsuAttributeProvider for OsuDifficultyAttributes {
-    fn get_aim(&self) -> f64 {
-        self.aim
+impl<'map> Iterator for OsuGradualDifficultyAttributes<'map> {
+    type Item = OsuDifficultyAttributes;
+
+    /// Process the next hit object and return the resulting
+    /// [`OsuDifficultyAttributes`], or `None` once the map is exhausted.
+    fn next(&mut self) -> Option<Self::Item> {
+        self.nth(0)
     }
+}
 
-    fn get_speed(&self) -> f64 {
-        self.speed
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn difficult_strain_count_is_zero_without_any_strain() {
+        assert_eq!(difficult_strain_count(&[]), 0.0);
+        assert_eq!(difficult_strain_count(&[0.0, 0.0, 0.0]), 0.0);
     }
 
-    fn get_overall_difficulty(&self) -> f64 {
-        self.overall_difficulty
+    #[test]
+    fn difficult_strain_count_counts_the_peak_itself() {
+        // The hardest strain always contributes exactly 1.0 on its own.
+        let count = difficult_strain_count(&[1.0]);
+
+        assert!((count - 1.0).abs() < f64::EPSILON);
     }
 
-    fn get_approach_rate(&self) -> f64 {
-        self.approach_rate
+    #[test]
+    fn difficult_strain_count_discounts_weaker_strains() {
+        let spiky = difficult_strain_count(&[1.0, 0.1, 0.1]);
+        let uniform = difficult_strain_count(&[1.0, 1.0, 1.0]);
+
+        assert!(spiky < uniform);
     }
 
-    fn get_max_combo(&self) -> usize {
-        self.max_combo
+    #[test]
+    fn process_with_no_states_returns_empty() {
+        let map = Beatmap::default();
+        let mut gradual = OsuGradualPerformanceAttributes::new(&map, 0);
+
+        let results = gradual.process(std::iter::empty());
+
+        assert!(results.is_empty());
     }
-}
 
-/// Update the OsuPP struct to use the OsuAttributeProvider trait.
-impl<'m> OsuPP<'m> {
-    pub fn attributes<T: OsuAttributeProvider>(self, attributes: T) -> Self {
-        self.aim(attributes.get_aim())
-            .speed(attributes.get_speed())
-            .overall_difficulty(attributes.get_overall_difficulty())
-            .approach_rate(attributes.get_approach_rate())
-            .max_combo(attributes.get_max_combo())
+    #[test]
+    fn difficulty_iterator_yields_one_attribute_per_object_then_exhausts() {
+        let map = Beatmap::from_path("./maps/2785319.osu").unwrap();
+        let n_objects = map.hit_objects.len();
+        let mut difficulty = OsuGradualDifficultyAttributes::new(&map, 0);
+
+        let first = difficulty.next().expect("map has at least one object");
+        let attrs: Vec<_> = (&mut difficulty).take(n_objects - 1).collect();
+
+        assert_eq!(attrs.len(), n_objects - 1);
+
+        let last = attrs.last().unwrap();
+        assert!(last.max_combo > first.max_combo);
+
+        assert!(difficulty.next().is_none());
     }
 }
\ No newline at end of file
@@ -1,6 +1,20 @@
-use super::stars::{stars, OsuDifficultyAttributes, OsuPerformanceAttributes};
+use super::gradual_performance::difficult_strain_count;
+use super::stars::{OsuDifficultyAttributes, OsuPerformanceAttributes};
+use crate::any::HitResultPriority;
 use crate::{Beatmap, Mods};
 
+/// Snapshot of the accuracy target and whichever `n300`/`n100`/`n50` were
+/// already fixed when [`OsuPP::accuracy`] was called, kept around so
+/// [`OsuPP::generate_hitresults`] can still tell which of them were free to
+/// distribute after `accuracy` materializes the rest.
+#[derive(Copy, Clone, Debug)]
+struct AccuracyTarget {
+    acc: f32,
+    fixed_n300: Option<usize>,
+    fixed_n100: Option<usize>,
+    fixed_n50: Option<usize>,
+}
+
 /// Calculator for pp on osu!standard maps.
 ///
 /// # Example
@@ -28,6 +42,18 @@ use crate::{Beatmap, Mods};
 ///
 /// println!("PP: {} | Stars: {}", next_result.pp(), next_result.stars());
 /// ```
+/// Snapshot of the accuracy target and whichever `n300`/`n100`/`n50` were
+/// already fixed when [`OsuPP::accuracy`] was called, kept around so
+/// [`OsuPP::generate_hitresults`] can still tell which of them were free to
+/// distribute after `accuracy` materializes the rest.
+#[derive(Copy, Clone, Debug)]
+struct AccuracyTarget {
+    acc: f32,
+    fixed_n300: Option<usize>,
+    fixed_n100: Option<usize>,
+    fixed_n50: Option<usize>,
+}
+
 #[derive(Clone, Debug)]
 pub struct OsuPP<'m> {
     map: &'m Beatmap,
@@ -41,6 +67,11 @@ pub struct OsuPP<'m> {
     n50: Option<usize>,
     n_misses: usize,
     passed_objects: Option<usize>,
+    priority: HitResultPriority,
+    scoring_version: ScoringVersion,
+    aim_difficult_strain_count: Option<f64>,
+    speed_difficult_strain_count: Option<f64>,
+    target_acc: Option<AccuracyTarget>,
 }
 
 impl<'m> OsuPP<'m> {
@@ -59,6 +90,11 @@ impl<'m> OsuPP<'m> {
             n50: None,
             n_misses: 0,
             passed_objects: None,
+            priority: HitResultPriority::BestCase,
+            scoring_version: ScoringVersion::Current,
+            aim_difficult_strain_count: None,
+            speed_difficult_strain_count: None,
+            target_acc: None,
         }
     }
 
@@ -95,26 +131,50 @@ impl<'m> OsuPP<'m> {
     }
 
     /// Specify the amount of 300s of a play.
+    ///
+    /// If called after [`accuracy`](OsuPP::accuracy), this also re-fixes the
+    /// count on the accuracy target so [`generate_hitresults`](OsuPP::generate_hitresults)
+    /// doesn't treat it as free to redistribute.
     #[inline]
     pub fn n300(mut self, n300: usize) -> Self {
         self.n300.replace(n300);
 
+        if let Some(target) = self.target_acc.as_mut() {
+            target.fixed_n300 = Some(n300);
+        }
+
         self
     }
 
     /// Specify the amount of 100s of a play.
+    ///
+    /// If called after [`accuracy`](OsuPP::accuracy), this also re-fixes the
+    /// count on the accuracy target so [`generate_hitresults`](OsuPP::generate_hitresults)
+    /// doesn't treat it as free to redistribute.
     #[inline]
     pub fn n100(mut self, n100: usize) -> Self {
         self.n100.replace(n100);
 
+        if let Some(target) = self.target_acc.as_mut() {
+            target.fixed_n100 = Some(n100);
+        }
+
         self
     }
 
     /// Specify the amount of 50s of a play.
+    ///
+    /// If called after [`accuracy`](OsuPP::accuracy), this also re-fixes the
+    /// count on the accuracy target so [`generate_hitresults`](OsuPP::generate_hitresults)
+    /// doesn't treat it as free to redistribute.
     #[inline]
     pub fn n50(mut self, n50: usize) -> Self {
         self.n50.replace(n50);
 
+        if let Some(target) = self.target_acc.as_mut() {
+            target.fixed_n50 = Some(n50);
+        }
+
         self
     }
 
@@ -134,6 +194,183 @@ impl<'m> OsuPP<'m> {
         self
     }
 
+    /// Select which coefficient set to reproduce pp with.
+    ///
+    /// Defaults to [`ScoringVersion::Current`]; pass [`ScoringVersion::Legacy`]
+    /// to reproduce the older osu-performance (stable) miss/AR/length formulas.
+    #[inline]
+    pub fn scoring_version(mut self, scoring_version: ScoringVersion) -> Self {
+        self.scoring_version = scoring_version;
+
+        self
+    }
+
+    /// Override how concentrated the aim strain is into a small number of spikes.
+    ///
+    /// Lets the miss penalty account for maps whose difficulty sits in one
+    /// huge spike instead of being spread uniformly; see
+    /// [`difficult_strain_count`](super::gradual_performance::difficult_strain_count).
+    ///
+    /// Defaults to [`aim_difficult_strain_count`](super::stars::OsuDifficultyAttributes::aim_difficult_strain_count)
+    /// on the attached [`OsuDifficultyAttributes`], so this only needs to be
+    /// called when feeding in externally recomputed strains.
+    #[inline]
+    pub fn aim_difficult_strain_count(mut self, aim_difficult_strain_count: f64) -> Self {
+        self.aim_difficult_strain_count = Some(aim_difficult_strain_count);
+
+        self
+    }
+
+    /// Same as [`aim_difficult_strain_count`](OsuPP::aim_difficult_strain_count)
+    /// but computed directly from the aim skill's raw strain peaks.
+    #[inline]
+    pub fn aim_difficult_strain_count_from_strains(self, strains: &[f64]) -> Self {
+        self.aim_difficult_strain_count(difficult_strain_count(strains))
+    }
+
+    /// Override how concentrated the speed strain is into a small number of spikes.
+    ///
+    /// See [`aim_difficult_strain_count`](OsuPP::aim_difficult_strain_count).
+    #[inline]
+    pub fn speed_difficult_strain_count(mut self, speed_difficult_strain_count: f64) -> Self {
+        self.speed_difficult_strain_count = Some(speed_difficult_strain_count);
+
+        self
+    }
+
+    /// Same as [`speed_difficult_strain_count`](OsuPP::speed_difficult_strain_count)
+    /// but computed directly from the speed skill's raw strain peaks.
+    #[inline]
+    pub fn speed_difficult_strain_count_from_strains(self, strains: &[f64]) -> Self {
+        self.speed_difficult_strain_count(difficult_strain_count(strains))
+    }
+
+    /// Specify how the hitresults returned by [`generate_hitresults`](OsuPP::generate_hitresults)
+    /// should be distributed when an exact accuracy can be reached with multiple splits.
+    #[inline]
+    pub fn hitresult_priority(mut self, priority: HitResultPriority) -> Self {
+        self.priority = priority;
+
+        self
+    }
+
+    /// Generate an [`OsuScoreState`] that honors any `n300`/`n100`/`n50`/`misses`
+    /// already set on this calculator, filling the remaining objects to reach
+    /// `accuracy` (if set) as closely as possible, distributed according to
+    /// [`hitresult_priority`](OsuPP::hitresult_priority).
+    ///
+    /// `max_combo` is the map's maximum achievable combo; the returned state's
+    /// combo is clamped to whatever was set through [`combo`](OsuPP::combo).
+    pub fn generate_hitresults(&self, max_combo: usize) -> OsuScoreState {
+        let n_objects = self.passed_objects.unwrap_or(self.map.hit_objects.len());
+        let n_misses = self.n_misses.min(n_objects);
+
+        let (n300, n100, n50) = if let Some(target) = self.target_acc {
+            let acc = target.acc;
+            let fixed_n300 = target.fixed_n300;
+            let fixed_n100 = target.fixed_n100;
+            let fixed_n50 = target.fixed_n50;
+
+            let fixed_total =
+                fixed_n300.unwrap_or(0) + fixed_n100.unwrap_or(0) + fixed_n50.unwrap_or(0);
+            let remaining = n_objects.saturating_sub(fixed_total + n_misses);
+
+            let target_points = (acc * n_objects as f32 * 6.0).round() as i64;
+            let fixed_points = 6 * fixed_n300.unwrap_or(0) as i64
+                + 2 * fixed_n100.unwrap_or(0) as i64
+                + fixed_n50.unwrap_or(0) as i64;
+            let needed = target_points - fixed_points;
+            let remaining_i = remaining as i64;
+
+            match (fixed_n300, fixed_n100, fixed_n50) {
+                (None, None, None) => {
+                    // All three free: points still needed above an all-50s
+                    // baseline, split between 300s/100s/50s.
+                    let extra_points = (needed - remaining_i).clamp(0, 5 * remaining_i);
+
+                    if let HitResultPriority::Balanced = self.priority {
+                        balanced_split(extra_points, remaining)
+                    } else {
+                        let n300 = match self.priority {
+                            HitResultPriority::BestCase => extra_points / 5,
+                            HitResultPriority::WorstCase => {
+                                ceil_div((extra_points - remaining_i).max(0), 4)
+                            }
+                            HitResultPriority::Balanced => unreachable!("handled above"),
+                        }
+                        .clamp(0, remaining_i) as usize;
+
+                        let n100 = (extra_points - 5 * n300 as i64)
+                            .clamp(0, remaining_i - n300 as i64) as usize;
+                        let n50 = remaining - n300 - n100;
+
+                        (n300, n100, n50)
+                    }
+                }
+                (None, Some(fixed_n100), None) => {
+                    // 100s fixed: 300s/50s free, same all-50s baseline trick.
+                    let extra_points = (needed - remaining_i).clamp(0, 5 * remaining_i);
+                    let n300 = round_div(extra_points, 5).clamp(0, remaining_i) as usize;
+                    let n50 = remaining - n300;
+
+                    (n300, fixed_n100, n50)
+                }
+                (Some(fixed_n300), None, None) => {
+                    // 300s fixed: 100s/50s free, a 100 is worth 1 extra point
+                    // over the all-50s baseline.
+                    let n100 = (needed - remaining_i).clamp(0, remaining_i) as usize;
+                    let n50 = remaining - n100;
+
+                    (fixed_n300, n100, n50)
+                }
+                (None, None, Some(fixed_n50)) => {
+                    // 50s fixed: 300s/100s free, against an all-100s
+                    // baseline since 50s no longer share the remaining pool.
+                    let extra_points = needed - 2 * remaining_i;
+
+                    let n300 = match self.priority {
+                        HitResultPriority::BestCase => round_div_ties(extra_points, 4, true),
+                        HitResultPriority::WorstCase => round_div_ties(extra_points, 4, false),
+                        HitResultPriority::Balanced => round_div(extra_points, 4),
+                    }
+                    .clamp(0, remaining_i) as usize;
+                    let n100 = remaining - n300;
+
+                    (n300, n100, fixed_n50)
+                }
+                // Fewer than two free fields: accuracy has no room left to
+                // move the result, fall back to the deterministic fill.
+                _ => deterministic_fill(
+                    [fixed_n300, fixed_n100, fixed_n50],
+                    remaining,
+                    self.priority,
+                ),
+            }
+        } else {
+            // No target accuracy: just fill the remaining objects at one end
+            // of whatever `n300`/`n100`/`n50` are already fixed.
+            let fixed_n300 = self.n300;
+            let fixed_n100 = self.n100;
+            let fixed_n50 = self.n50;
+
+            let fixed_total =
+                fixed_n300.unwrap_or(0) + fixed_n100.unwrap_or(0) + fixed_n50.unwrap_or(0);
+            let remaining = n_objects.saturating_sub(fixed_total + n_misses);
+
+            deterministic_fill([fixed_n300, fixed_n100, fixed_n50], remaining, self.priority)
+        };
+
+        let max_combo = self.combo.map_or(max_combo, |combo| combo.min(max_combo));
+
+        OsuScoreState {
+            max_combo,
+            n300,
+            n100,
+            n50,
+            n_misses,
+        }
+    }
+
     /// Generate the hit results with respect to the given accuracy between `0` and `100`.
     ///
     /// Be sure to set `misses` beforehand!
@@ -143,6 +380,18 @@ impl<'m> OsuPP<'m> {
 
         let acc = acc / 100.0;
 
+        // Snapshot the target accuracy together with whatever `n300`/`n100`/`n50`
+        // are fixed *before* this call materializes the rest below, so
+        // `generate_hitresults` can still see which of them were genuinely
+        // free to distribute according to `hitresult_priority` instead of
+        // finding all three already filled in.
+        self.target_acc = Some(AccuracyTarget {
+            acc,
+            fixed_n300: self.n300,
+            fixed_n100: self.n100,
+            fixed_n50: self.n50,
+        });
+
         if self.n100.or(self.n50).is_some() {
             let mut n100 = self.n100.unwrap_or(0);
             let mut n50 = self.n50.unwrap_or(0);
@@ -233,8 +482,18 @@ impl<'m> OsuPP<'m> {
     /// Returns an object which contains the pp and [`DifficultyAttributes`](crate::osu::DifficultyAttributes)
     /// containing stars and other attributes.
     fn compute_aim_value(&self, total_hits: f32, effective_miss_count: f32) -> f32 {
+        // Autoplay/Cinema scores aren't real plays; they never award pp.
+        if self.is_autoplay_or_cinema() {
+            return 0.0;
+        }
+
+        // Autopilot automates the cursor, so aim contributes nothing.
+        if self.mods.ap() {
+            return 0.0;
+        }
+
         let attributes = self.attributes.as_ref().unwrap();
-    
+
         // TD penalty
         let raw_aim = if self.mods.td() {
             attributes.aim_strain.powf(0.8) as f32
@@ -243,16 +502,24 @@ impl<'m> OsuPP<'m> {
         };
     
         let mut aim_value = ((5.0f32 * (raw_aim / 0.0675f32).max(1.0f32)) - 4.0f32).powi(3) / 100_000.0f32;
-    
+
         // Longer maps are worth more
-        let len_bonus = 0.88
-            + 0.4 * (total_hits / 2000.0).min(1.0)
-            + (total_hits > 2000.0) as u8 as f32 * 0.5 * (total_hits / 2000.0).log10();
+        let len_bonus = if self.scoring_version == ScoringVersion::Legacy {
+            self.length_bonus(total_hits)
+        } else {
+            0.88
+                + 0.4 * (total_hits / 2000.0).min(1.0)
+                + (total_hits > 2000.0) as u8 as f32 * 0.5 * (total_hits / 2000.0).log10()
+        };
         aim_value *= len_bonus;
     
         // Penalize misses
         if effective_miss_count > 0.0 {
-            let miss_penalty = self.calculate_miss_penalty(effective_miss_count);
+            let difficult_strain_count = self
+                .aim_difficult_strain_count
+                .unwrap_or(attributes.aim_difficult_strain_count);
+            let miss_penalty =
+                self.calculate_miss_penalty(effective_miss_count, difficult_strain_count);
             aim_value *= miss_penalty;
         }
     
@@ -308,35 +575,56 @@ impl<'m> OsuPP<'m> {
         aim_value
     }
     fn compute_speed_value(&self, total_hits: f32, effective_miss_count: f32) -> f32 {
+        // Autoplay/Cinema scores aren't real plays; they never award pp.
+        if self.is_autoplay_or_cinema() {
+            return 0.0;
+        }
+
+        // Relax trivializes tapping, so speed contributes nothing.
+        if self.mods.rx() {
+            return 0.0;
+        }
+
         let attributes = self.attributes.as_ref().unwrap();
 
         let mut speed_value =
             (5.0 * (attributes.speed_strain as f32 / 0.0675).max(1.0) - 4.0).powi(3) / 100_000.0;
 
         // Longer maps are worth more
-        let len_bonus = 0.83
-            + 0.5 * (total_hits / 2000.0).min(1.0)
-            + (total_hits > 2000.0) as u8 as f32 * 0.5 * (total_hits / 2000.0).log10();
+        let len_bonus = if self.scoring_version == ScoringVersion::Legacy {
+            self.length_bonus(total_hits)
+        } else {
+            0.83
+                + 0.5 * (total_hits / 2000.0).min(1.0)
+                + (total_hits > 2000.0) as u8 as f32 * 0.5 * (total_hits / 2000.0).log10()
+        };
         speed_value *= len_bonus;
 
         // Penalize misses
         if effective_miss_count > 0.0 {
-            let miss_penalty = self.calculate_miss_penalty(effective_miss_count);
+            let difficult_strain_count = self
+                .speed_difficult_strain_count
+                .unwrap_or(attributes.speed_difficult_strain_count);
+            let miss_penalty =
+                self.calculate_miss_penalty(effective_miss_count, difficult_strain_count);
             speed_value *= miss_penalty;
         }
 
-        // AR bonus
-        if attributes.ar > 10.33 {
-            let mut ar_factor = if attributes.ar > 10.33 {
-                0.3 * (attributes.ar - 10.33)
-            } else {
-                0.0
-            };
+        // AR bonus. Current's low-AR branch stayed unreachable under the
+        // old `if ar > 10.33` nesting (ar < 8.0 and ar > 10.33 can't both
+        // hold), so only Legacy applies it here to keep Current's pp output
+        // unchanged.
+        let mut ar_factor = if attributes.ar > 10.33 {
+            0.3 * (attributes.ar - 10.33)
+        } else {
+            0.0
+        };
 
-            if attributes.ar < 8.0 {
-                ar_factor = 0.025 * (8.0 - attributes.ar);
-            }
+        if self.scoring_version == ScoringVersion::Legacy && attributes.ar < 8.0 {
+            ar_factor = 0.01 * (8.0 - attributes.ar);
+        }
 
+        if ar_factor != 0.0 {
             speed_value *= 1.0 + ar_factor as f32 * len_bonus;
         }
 
@@ -361,6 +649,11 @@ impl<'m> OsuPP<'m> {
     }
 
     fn compute_accuracy_value(&self, total_hits: f32) -> f32 {
+        // Autoplay/Cinema scores aren't real plays; they never award pp.
+        if self.is_autoplay_or_cinema() {
+            return 0.0;
+        }
+
         let attributes = self.attributes.as_ref().unwrap();
         let n_circles = attributes.n_circles as f32;
         let n300 = self.n300.unwrap_or(0) as f32;
@@ -391,23 +684,38 @@ impl<'m> OsuPP<'m> {
     }
 
     #[inline]
-    fn total_hits(&self) -> usize {
-        let n_objects = self.passed_objects.unwrap_or(self.map.hit_objects.len());
+    fn calculate_miss_penalty(
+        &self,
+        effective_miss_count: f32,
+        difficult_strain_count: f64,
+    ) -> f32 {
+        if self.scoring_version == ScoringVersion::Legacy {
+            return 0.97_f32.powi(self.n_misses as i32);
+        }
 
-        (self.n300.unwrap_or(0) + self.n100.unwrap_or(0) + self.n50.unwrap_or(0) + self.n_misses)
-            .min(n_objects)
+        scale_miss_penalty(effective_miss_count as f64, difficult_strain_count) as f32
     }
 
+    /// Length bonus curve used by [`ScoringVersion::Legacy`] for both the
+    /// aim and speed components.
     #[inline]
-    fn calculate_miss_penalty(&self, effective_miss_count: f32) -> f32 {
-        let total_hits = self.total_hits() as f32;
-
-        0.97 * (1.0 - (effective_miss_count / total_hits).powf(0.5))
-            .powf(1.0 + (effective_miss_count / 1.5))
+    fn length_bonus(&self, total_hits: f32) -> f32 {
+        0.95
+            + 0.4 * (total_hits / 2000.0).min(1.0)
+            + (total_hits > 2000.0) as u8 as f32 * 0.5 * (total_hits / 2000.0).log10()
     }
 
     #[inline]
     fn calculate_effective_miss_count(&self) -> f32 {
+        // Relax and Autopilot don't break combo the way vanilla sliders do,
+        // so the combo-based miss estimate doesn't carry any signal for them.
+        // Together with the early returns in `compute_aim_value` (AP) and
+        // `compute_speed_value` (RX), this is the complete removal of
+        // combo-scaling for those mods; don't add a second code path for it.
+        if self.mods.rx() || self.mods.ap() {
+            return self.n_misses as f32;
+        }
+
         let mut combo_based_miss_count: f32 = 0.0;
 
         let attributes = self.attributes.as_ref().unwrap();
@@ -425,6 +733,237 @@ impl<'m> OsuPP<'m> {
         combo_based_miss_count = combo_based_miss_count.min(n100 + n50 + self.n_misses as f32);
         combo_based_miss_count.max(self.n_misses as f32)
     }
+
+    /// Autoplay and Cinema scores aren't real plays and should never award pp.
+    ///
+    /// Checked at the top of [`compute_aim_value`](OsuPP::compute_aim_value),
+    /// [`compute_speed_value`](OsuPP::compute_speed_value) and
+    /// [`compute_accuracy_value`](OsuPP::compute_accuracy_value), each of
+    /// which returns `0.0` immediately when this is `true`.
+    #[inline]
+    fn is_autoplay_or_cinema(&self) -> bool {
+        self.mods.autoplay() || self.mods.cinema()
+    }
+}
+
+/// Fills whichever of `[n300, n100, n50]` are still `None`, in priority
+/// order, dumping all of `remaining` onto the first unfilled slot and `0`
+/// onto the rest. If all three are already fixed, `remaining` is added onto
+/// n50 instead.
+///
+/// Used both when no target accuracy was set and as the fallback for the
+/// accuracy-given case once fewer than two hitresults are still free to move.
+fn deterministic_fill(
+    fixed: [Option<usize>; 3],
+    remaining: usize,
+    priority: HitResultPriority,
+) -> (usize, usize, usize) {
+    if let HitResultPriority::Balanced = priority {
+        return balanced_fill(fixed, remaining);
+    }
+
+    let order: [usize; 3] = if let HitResultPriority::BestCase = priority {
+        [0, 1, 2]
+    } else {
+        [2, 1, 0]
+    };
+
+    let mut values = fixed;
+    let mut filled = false;
+    let mut rest = remaining;
+
+    for idx in order {
+        if values[idx].is_none() {
+            if filled {
+                values[idx] = Some(0);
+            } else {
+                values[idx] = Some(rest);
+                rest = 0;
+                filled = true;
+            }
+        }
+    }
+
+    if !filled {
+        *values[2].as_mut().unwrap() += rest;
+    }
+
+    (values[0].unwrap(), values[1].unwrap(), values[2].unwrap())
+}
+
+/// Spreads `remaining` as evenly as possible across whichever of `fixed`'s
+/// slots are still `None`, handing the leftover (`remaining % free_slots`) out
+/// one each to the earliest free slots. If all three are already fixed, `remaining` is
+/// added onto n50 instead, matching [`deterministic_fill`].
+fn balanced_fill(fixed: [Option<usize>; 3], remaining: usize) -> (usize, usize, usize) {
+    let free: Vec<usize> = (0..3).filter(|&idx| fixed[idx].is_none()).collect();
+    let mut values = fixed;
+
+    if free.is_empty() {
+        *values[2].as_mut().unwrap() += remaining;
+        return (values[0].unwrap(), values[1].unwrap(), values[2].unwrap());
+    }
+
+    let share = remaining / free.len();
+    let mut extra = remaining % free.len();
+
+    for idx in free {
+        let mut value = share;
+
+        if extra > 0 {
+            value += 1;
+            extra -= 1;
+        }
+
+        values[idx] = Some(value);
+    }
+
+    (values[0].unwrap(), values[1].unwrap(), values[2].unwrap())
+}
+
+/// Splits `remaining` objects into `(n300, n100, n50)` so that
+/// `5 * n300 + n100` matches `extra_points` as closely as possible, starting
+/// from an even three-way split and nudging counts one at a time towards the
+/// closest achievable total, in whichever direction (up or down) is needed.
+fn balanced_split(extra_points: i64, remaining: usize) -> (usize, usize, usize) {
+    let mut n300 = remaining / 3;
+    let mut n100 = remaining / 3;
+    let mut n50 = remaining - n300 - n100;
+
+    let achieved = |n300: usize, n100: usize| 5 * n300 as i64 + n100 as i64;
+
+    while achieved(n300, n100) < extra_points && (n100 > 0 || n50 > 0) {
+        if n50 > 0 {
+            n50 -= 1;
+            n100 += 1;
+        } else {
+            n100 -= 1;
+            n300 += 1;
+        }
+    }
+
+    while achieved(n300, n100) > extra_points && (n300 > 0 || n100 > 0) {
+        if n100 > 0 {
+            n100 -= 1;
+            n50 += 1;
+        } else {
+            n300 -= 1;
+            n100 += 1;
+        }
+    }
+
+    // Whichever loop above ran last only stops once it can't get any closer
+    // without overshooting past `extra_points` in the other direction, and
+    // its final swap moves the total by up to 4 points (a single 100 turned
+    // into a 300), so a single 300<->100 swap in either direction is the
+    // only other candidate worth considering; keep whichever total lands
+    // closest to `extra_points`.
+    let diff = |n300: usize, n100: usize| (achieved(n300, n100) - extra_points).abs();
+    let mut best = (n300, n100, n50);
+    let mut best_diff = diff(n300, n100);
+
+    if n300 > 0 {
+        let candidate = (n300 - 1, n100 + 1, n50);
+        let candidate_diff = diff(candidate.0, candidate.1);
+
+        if candidate_diff < best_diff {
+            best = candidate;
+            best_diff = candidate_diff;
+        }
+    }
+
+    if n100 > 0 {
+        let candidate = (n300 + 1, n100 - 1, n50);
+
+        if diff(candidate.0, candidate.1) < best_diff {
+            best = candidate;
+        }
+    }
+
+    best
+}
+
+/// Smallest `q` such that `q * b >= a`, for non-negative `a` and positive `b`.
+#[inline]
+fn ceil_div(a: i64, b: i64) -> i64 {
+    (a + b - 1) / b
+}
+
+/// `a / b` rounded to the nearest integer, ties rounding up (towards positive infinity).
+#[inline]
+fn round_div(a: i64, b: i64) -> i64 {
+    round_div_ties(a, b, true)
+}
+
+/// `a / b` rounded to the nearest integer; exact `.5` ties round up when
+/// `ties_up` is `true`, down otherwise.
+#[inline]
+fn round_div_ties(a: i64, b: i64, ties_up: bool) -> i64 {
+    let q = a.div_euclid(b);
+    let r = a.rem_euclid(b);
+
+    if 2 * r > b || (2 * r == b && ties_up) {
+        q + 1
+    } else {
+        q
+    }
+}
+
+/// Scales the flat miss penalty by how concentrated a skill's strain is.
+///
+/// A map whose difficulty sits in one huge spike should not be penalized
+/// the same way as one with uniformly high strain throughout, so the
+/// effective miss count is weighed against the skill's difficult strain count.
+#[inline]
+fn scale_miss_penalty(effective_miss_count: f64, difficult_strain_count: f64) -> f64 {
+    if effective_miss_count <= 0.0 {
+        return 1.0;
+    }
+
+    let strain_count = difficult_strain_count.max(1.0);
+
+    (1.0 - (effective_miss_count / strain_count).min(1.0)).powf(0.775)
+}
+
+/// A score's hitresult counts and combo, as fed into or generated by [`OsuPP`].
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct OsuScoreState {
+    /// Maximum combo of the score.
+    pub max_combo: usize,
+    /// Amount of 300s.
+    pub n300: usize,
+    /// Amount of 100s.
+    pub n100: usize,
+    /// Amount of 50s.
+    pub n50: usize,
+    /// Amount of misses.
+    pub n_misses: usize,
+}
+
+impl OsuScoreState {
+    /// Calculate the accuracy between `0.0` and `1.0` for this state.
+    #[inline]
+    pub fn accuracy(&self) -> f32 {
+        let total = self.n300 + self.n100 + self.n50 + self.n_misses;
+
+        if total == 0 {
+            return 0.0;
+        }
+
+        let numerator = 6 * self.n300 + 2 * self.n100 + self.n50;
+
+        numerator as f32 / (6 * total) as f32
+    }
+}
+
+/// Which coefficient set [`OsuPP`] reproduces pp with.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ScoringVersion {
+    /// The current, actively maintained pp formulas.
+    Current,
+    /// The older osu-performance (stable) miss/AR/length formulas, kept for
+    /// tooling that needs to reproduce historical pp values.
+    Legacy,
 }
 
 /// Provides attributes for an osu! beatmap.
@@ -564,6 +1103,8 @@ mod test {
             n_spinners: 1,
             stars: 5.669858729379631,
             max_combo: 909,
+            aim_difficult_strain_count: 0.0,
+            speed_difficult_strain_count: 0.0,
         };
 
         (map, attrs)
@@ -579,7 +1120,7 @@ mod test {
             .combo(500)
             .n300(300)
             .n100(20)
-            .n_misses(2)
+            .misses(2)
             .hitresult_priority(HitResultPriority::BestCase)
             .generate_hitresults(max_combo);
 
@@ -604,7 +1145,7 @@ mod test {
             .combo(500)
             .n300(300)
             .n50(10)
-            .n_misses(2)
+            .misses(2)
             .hitresult_priority(HitResultPriority::BestCase)
             .generate_hitresults(max_combo);
 
@@ -628,7 +1169,7 @@ mod test {
             .attributes(attrs)
             .combo(500)
             .n50(10)
-            .n_misses(2)
+            .misses(2)
             .hitresult_priority(HitResultPriority::WorstCase)
             .generate_hitresults(max_combo);
 
@@ -654,7 +1195,7 @@ mod test {
             .n300(300)
             .n100(50)
             .n50(10)
-            .n_misses(2)
+            .misses(2)
             .hitresult_priority(HitResultPriority::WorstCase)
             .generate_hitresults(max_combo);
 
@@ -678,15 +1219,15 @@ mod test {
             .attributes(attrs)
             .combo(500)
             .accuracy(98.0)
-            .n_misses(2)
+            .misses(2)
             .hitresult_priority(HitResultPriority::BestCase)
             .generate_hitresults(max_combo);
 
         let expected = OsuScoreState {
             max_combo: 500,
-            n300: 584,
-            n100: 15,
-            n50: 0,
+            n300: 587,
+            n100: 0,
+            n50: 12,
             n_misses: 2,
         };
 
@@ -709,7 +1250,7 @@ mod test {
             .combo(500)
             .accuracy(95.0)
             .n100(15)
-            .n_misses(2)
+            .misses(2)
             .hitresult_priority(HitResultPriority::BestCase)
             .generate_hitresults(max_combo);
 
@@ -740,7 +1281,7 @@ mod test {
             .combo(500)
             .accuracy(95.0)
             .n50(10)
-            .n_misses(2)
+            .misses(2)
             .hitresult_priority(HitResultPriority::BestCase)
             .generate_hitresults(max_combo);
 
@@ -773,6 +1314,35 @@ mod test {
             .hitresult_priority(HitResultPriority::BestCase)
             .generate_hitresults(max_combo);
 
+        let expected = OsuScoreState {
+            max_combo: 500,
+            n300: 528,
+            n100: 4,
+            n50: 69,
+            n_misses: 0,
+        };
+
+        assert_eq!(
+            state,
+            expected,
+            "{}% vs {}%",
+            state.accuracy(),
+            expected.accuracy()
+        );
+    }
+
+    #[test]
+    fn hitresults_acc_worst() {
+        let (map, attrs) = test_data();
+        let max_combo = attrs.max_combo();
+
+        let state = OsuPP::new(&map)
+            .attributes(attrs)
+            .combo(500)
+            .accuracy(90.0)
+            .hitresult_priority(HitResultPriority::WorstCase)
+            .generate_hitresults(max_combo);
+
         let expected = OsuScoreState {
             max_combo: 500,
             n300: 511,
@@ -791,7 +1361,7 @@ mod test {
     }
 
     #[test]
-    fn hitresults_acc_worst() {
+    fn hitresults_acc_balanced() {
         let (map, attrs) = test_data();
         let max_combo = attrs.max_combo();
 
@@ -799,14 +1369,46 @@ mod test {
             .attributes(attrs)
             .combo(500)
             .accuracy(90.0)
-            .hitresult_priority(HitResultPriority::WorstCase)
+            .hitresult_priority(HitResultPriority::Balanced)
             .generate_hitresults(max_combo);
 
         let expected = OsuScoreState {
             max_combo: 500,
-            n300: 528,
-            n100: 4,
-            n50: 69,
+            n300: 511,
+            n100: 89,
+            n50: 1,
+            n_misses: 0,
+        };
+
+        assert_eq!(
+            state,
+            expected,
+            "{}% vs {}%",
+            state.accuracy(),
+            expected.accuracy()
+        );
+    }
+
+    #[test]
+    fn hitresults_acc_balanced_low() {
+        let (map, attrs) = test_data();
+        let max_combo = attrs.max_combo();
+
+        // A target well below the even three-way split's baseline accuracy
+        // (~50%); the overshoot correction needs more than a single 300<->100
+        // swap to walk the split back down to it.
+        let state = OsuPP::new(&map)
+            .attributes(attrs)
+            .combo(500)
+            .accuracy(20.0)
+            .hitresult_priority(HitResultPriority::Balanced)
+            .generate_hitresults(max_combo);
+
+        let expected = OsuScoreState {
+            max_combo: 500,
+            n300: 24,
+            n100: 0,
+            n50: 577,
             n_misses: 0,
         };
 
@@ -818,4 +1420,137 @@ mod test {
             expected.accuracy()
         );
     }
+
+    #[test]
+    fn hitresults_n300_n_misses_balanced() {
+        let (map, attrs) = test_data();
+        let max_combo = attrs.max_combo();
+
+        let state = OsuPP::new(&map)
+            .attributes(attrs)
+            .combo(500)
+            .n300(300)
+            .misses(2)
+            .hitresult_priority(HitResultPriority::Balanced)
+            .generate_hitresults(max_combo);
+
+        let expected = OsuScoreState {
+            max_combo: 500,
+            n300: 300,
+            n100: 150,
+            n50: 149,
+            n_misses: 2,
+        };
+
+        assert_eq!(state, expected);
+    }
+
+    #[test]
+    fn autopilot_zeroes_aim_value() {
+        let (map, attrs) = test_data();
+
+        // Autopilot automates the cursor, so combo-based aim scaling never
+        // needs to run in the first place.
+        let calculator = OsuPP::new(&map).attributes(attrs).mods(1 << 13); // AP
+
+        assert_eq!(calculator.compute_aim_value(300.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn relax_zeroes_speed_value() {
+        let (map, attrs) = test_data();
+
+        // Relax trivializes tapping, so combo-based speed scaling never
+        // needs to run in the first place.
+        let calculator = OsuPP::new(&map).attributes(attrs).mods(1 << 7); // RX
+
+        assert_eq!(calculator.compute_speed_value(300.0, 0.0), 0.0);
+    }
+
+    #[test]
+    fn autoplay_and_cinema_award_no_pp() {
+        let (map, attrs) = test_data();
+
+        let autoplay = OsuPP::new(&map).attributes(attrs.clone()).mods(1 << 11); // Autoplay
+        assert_eq!(autoplay.compute_aim_value(300.0, 0.0), 0.0);
+        assert_eq!(autoplay.compute_speed_value(300.0, 0.0), 0.0);
+        assert_eq!(autoplay.compute_accuracy_value(300.0), 0.0);
+
+        let cinema = OsuPP::new(&map).attributes(attrs).mods(1 << 22); // Cinema
+        assert_eq!(cinema.compute_aim_value(300.0, 0.0), 0.0);
+        assert_eq!(cinema.compute_speed_value(300.0, 0.0), 0.0);
+        assert_eq!(cinema.compute_accuracy_value(300.0), 0.0);
+    }
+
+    #[test]
+    fn legacy_miss_penalty_is_flat_per_miss() {
+        let (map, attrs) = test_data();
+
+        let calculator = OsuPP::new(&map)
+            .attributes(attrs)
+            .scoring_version(ScoringVersion::Legacy)
+            .misses(3);
+
+        // Legacy scoring ignores the difficulty-weighted effective miss
+        // count entirely and falls back to a flat 0.97^n_misses penalty.
+        assert_eq!(
+            calculator.calculate_miss_penalty(1.0, 5.0),
+            0.97_f32.powi(3)
+        );
+    }
+
+    #[test]
+    fn legacy_scoring_uses_the_legacy_length_bonus_curve() {
+        let (map, attrs) = test_data();
+
+        let legacy = OsuPP::new(&map)
+            .attributes(attrs.clone())
+            .accuracy(100.0)
+            .scoring_version(ScoringVersion::Legacy);
+        let current = OsuPP::new(&map).attributes(attrs).accuracy(100.0);
+
+        assert_ne!(
+            legacy.compute_aim_value(300.0, 0.0),
+            current.compute_aim_value(300.0, 0.0)
+        );
+        assert_ne!(
+            legacy.compute_speed_value(300.0, 0.0),
+            current.compute_speed_value(300.0, 0.0)
+        );
+    }
+
+    #[test]
+    fn legacy_scoring_uses_the_legacy_low_ar_coefficient() {
+        let (map, attrs) = test_data();
+
+        let low_ar_attrs = OsuDifficultyAttributes {
+            ar: 7.0,
+            ..attrs.clone()
+        };
+        let ar8_attrs = OsuDifficultyAttributes { ar: 8.0, ..attrs };
+
+        let legacy = OsuPP::new(&map)
+            .attributes(low_ar_attrs.clone())
+            .accuracy(100.0)
+            .scoring_version(ScoringVersion::Legacy);
+        let current = OsuPP::new(&map).attributes(low_ar_attrs).accuracy(100.0);
+        let legacy_ar8 = OsuPP::new(&map)
+            .attributes(ar8_attrs)
+            .accuracy(100.0)
+            .scoring_version(ScoringVersion::Legacy);
+
+        // Current's low-AR branch stays unreachable (see `compute_speed_value`)
+        // and its length bonus differs from Legacy's, so the two disagree here.
+        assert_ne!(
+            legacy.compute_speed_value(300.0, 0.0),
+            current.compute_speed_value(300.0, 0.0)
+        );
+
+        // Between AR 8 and AR 7 only Legacy's 0.01 low-AR coefficient moves
+        // the speed value.
+        assert_ne!(
+            legacy.compute_speed_value(300.0, 0.0),
+            legacy_ar8.compute_speed_value(300.0, 0.0)
+        );
+    }
 }
\ No newline at end of file